@@ -0,0 +1,283 @@
+//! Byte-accurate bencode decoding.
+//!
+//! Bencoded data is parsed directly from `&[u8]` so that binary fields like
+//! `pieces` (and the raw `info` dictionary, located by
+//! [`find_top_level_value_span`]) survive intact instead of being lossily
+//! reinterpreted as UTF-8.
+
+use crate::error::TorrentError;
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// A decoded bencode value.
+///
+/// Byte strings are kept as raw `Vec<u8>` rather than `String`, since
+/// bencode byte strings carry arbitrary binary data.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Value {
+    Bytes(Vec<u8>),
+    Int(i64),
+    List(Vec<Value>),
+    Dict(BTreeMap<Vec<u8>, Value>),
+}
+
+impl Value {
+    pub fn as_bytes(&self) -> Option<&[u8]> {
+        match self {
+            Value::Bytes(b) => Some(b),
+            _ => None,
+        }
+    }
+
+    pub fn as_int(&self) -> Option<i64> {
+        match self {
+            Value::Int(n) => Some(*n),
+            _ => None,
+        }
+    }
+
+    pub fn as_list(&self) -> Option<&[Value]> {
+        match self {
+            Value::List(l) => Some(l),
+            _ => None,
+        }
+    }
+
+    pub fn as_dict(&self) -> Option<&BTreeMap<Vec<u8>, Value>> {
+        match self {
+            Value::Dict(d) => Some(d),
+            _ => None,
+        }
+    }
+
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        self.as_dict()?.get(key.as_bytes())
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Bytes(b) => write!(f, "{:?}", String::from_utf8_lossy(b)),
+            Value::Int(n) => write!(f, "{}", n),
+            Value::List(items) => {
+                write!(f, "[")?;
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, "{}", item)?;
+                }
+                write!(f, "]")
+            }
+            Value::Dict(map) => {
+                write!(f, "{{")?;
+                for (i, (key, value)) in map.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, "{:?}:{}", String::from_utf8_lossy(key), value)?;
+                }
+                write!(f, "}}")
+            }
+        }
+    }
+}
+
+/// Decodes a single bencoded value starting at the front of `encoded`.
+///
+/// Returns the decoded value along with the number of bytes it consumed, so
+/// callers parsing lists and dicts can advance past nested values by index.
+pub fn decode(encoded: &[u8]) -> Result<(Value, usize), TorrentError> {
+    match encoded.first() {
+        Some(b) if b.is_ascii_digit() => decode_bytes(encoded),
+        Some(b'i') => decode_int(encoded),
+        Some(b'l') => decode_list(encoded),
+        Some(b'd') => decode_dict(encoded),
+        Some(other) => Err(TorrentError::InvalidBencode(format!(
+            "unhandled or invalid bencoded value starting with {:?}",
+            *other as char
+        ))),
+        None => Err(TorrentError::InvalidBencode(
+            "empty buffer while expecting bencoded data".to_string(),
+        )),
+    }
+}
+
+fn decode_bytes(encoded: &[u8]) -> Result<(Value, usize), TorrentError> {
+    let colon_index = encoded
+        .iter()
+        .position(|&b| b == b':')
+        .ok_or_else(|| TorrentError::InvalidBencode("missing ':' in bencoded string".to_string()))?;
+    let length: usize = std::str::from_utf8(&encoded[..colon_index])
+        .map_err(|_| TorrentError::InvalidBencode("string length prefix wasn't valid UTF-8".to_string()))?
+        .parse()
+        .map_err(|_| TorrentError::InvalidBencode("string length prefix wasn't a number".to_string()))?;
+
+    let start_of_str = colon_index + 1;
+    let end_of_str = start_of_str.checked_add(length).ok_or_else(|| {
+        TorrentError::InvalidBencode(format!(
+            "string length {} overflows when added to offset {}",
+            length, start_of_str
+        ))
+    })?;
+    if end_of_str > encoded.len() {
+        return Err(TorrentError::InvalidBencode(format!(
+            "string length {} extends beyond encoded data length {}",
+            length,
+            encoded.len()
+        )));
+    }
+
+    Ok((
+        Value::Bytes(encoded[start_of_str..end_of_str].to_vec()),
+        end_of_str,
+    ))
+}
+
+fn decode_int(encoded: &[u8]) -> Result<(Value, usize), TorrentError> {
+    let end_index = encoded
+        .iter()
+        .position(|&b| b == b'e')
+        .ok_or_else(|| TorrentError::InvalidBencode("missing 'e' terminator for integer".to_string()))?;
+    let number: i64 = std::str::from_utf8(&encoded[1..end_index])
+        .map_err(|_| TorrentError::InvalidBencode("integer wasn't valid UTF-8".to_string()))?
+        .parse()
+        .map_err(|_| TorrentError::InvalidBencode("integer wasn't a valid number".to_string()))?;
+
+    Ok((Value::Int(number), end_index + 1))
+}
+
+fn decode_list(encoded: &[u8]) -> Result<(Value, usize), TorrentError> {
+    let mut list = Vec::new();
+    let mut index = 1; // after 'l'
+
+    loop {
+        match encoded.get(index) {
+            Some(b'e') => break,
+            Some(_) => {
+                let (value, used) = decode(&encoded[index..])?;
+                list.push(value);
+                index += used;
+            }
+            None => {
+                return Err(TorrentError::InvalidBencode(
+                    "list ran out of bytes before 'e' terminator".to_string(),
+                ))
+            }
+        }
+    }
+
+    Ok((Value::List(list), index + 1))
+}
+
+fn decode_dict(encoded: &[u8]) -> Result<(Value, usize), TorrentError> {
+    let mut map = BTreeMap::new();
+    let mut index = 1; // after 'd'
+
+    loop {
+        match encoded.get(index) {
+            Some(b'e') => break,
+            Some(_) => {
+                let (key, used_key) = decode_bytes(&encoded[index..])?;
+                index += used_key;
+
+                let (value, used_val) = decode(&encoded[index..])?;
+                index += used_val;
+
+                let key = match key {
+                    Value::Bytes(b) => b,
+                    _ => unreachable!("decode_bytes always returns Value::Bytes"),
+                };
+                map.insert(key, value);
+            }
+            None => {
+                return Err(TorrentError::InvalidBencode(
+                    "dict ran out of bytes before 'e' terminator".to_string(),
+                ))
+            }
+        }
+    }
+
+    Ok((Value::Dict(map), index + 1))
+}
+
+/// Locates the byte span of a top-level dictionary key's value within the
+/// original encoded buffer.
+///
+/// Returns `(start, end)` such that `buffer[start..end]` is exactly the
+/// bencoded bytes for `key` as they appeared in the source file, with no
+/// re-serialization involved. This is what makes it possible to hash a
+/// sub-dictionary (like `info`) correctly even when it contains fields the
+/// caller doesn't model or keys in an order a re-encoder wouldn't reproduce.
+pub fn find_top_level_value_span(
+    buffer: &[u8],
+    key: &str,
+) -> Result<Option<(usize, usize)>, TorrentError> {
+    if buffer.first() != Some(&b'd') {
+        return Ok(None);
+    }
+
+    let mut index = 1; // after 'd'
+    while buffer.get(index) != Some(&b'e') {
+        let (key_val, used_key) = decode_bytes(&buffer[index..])?;
+        index += used_key;
+
+        let value_start = index;
+        let (_, used_val) = decode(&buffer[index..])?;
+        let value_end = value_start + used_val;
+        index = value_end;
+
+        if key_val.as_bytes() == Some(key.as_bytes()) {
+            return Ok(Some((value_start, value_end)));
+        }
+    }
+
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decodes_byte_strings() {
+        let (value, used) = decode(b"4:spam").unwrap();
+        assert_eq!(value, Value::Bytes(b"spam".to_vec()));
+        assert_eq!(used, 6);
+    }
+
+    #[test]
+    fn decodes_integers() {
+        let (value, used) = decode(b"i-42e").unwrap();
+        assert_eq!(value, Value::Int(-42));
+        assert_eq!(used, 5);
+    }
+
+    #[test]
+    fn decodes_lists_and_dicts() {
+        let (value, _) = decode(b"l4:spami42ee").unwrap();
+        assert_eq!(
+            value,
+            Value::List(vec![Value::Bytes(b"spam".to_vec()), Value::Int(42)])
+        );
+
+        let (value, _) = decode(b"d3:cow3:moo4:spam4:eggse").unwrap();
+        let mut expected = BTreeMap::new();
+        expected.insert(b"cow".to_vec(), Value::Bytes(b"moo".to_vec()));
+        expected.insert(b"spam".to_vec(), Value::Bytes(b"eggs".to_vec()));
+        assert_eq!(value, Value::Dict(expected));
+    }
+
+    #[test]
+    fn rejects_string_length_prefix_that_overflows_usize() {
+        let err = decode(b"18446744073709551615:abc").unwrap_err();
+        assert!(matches!(err, TorrentError::InvalidBencode(_)));
+    }
+
+    #[test]
+    fn rejects_string_length_prefix_past_end_of_buffer() {
+        let err = decode(b"10:abc").unwrap_err();
+        assert!(matches!(err, TorrentError::InvalidBencode(_)));
+    }
+}
@@ -0,0 +1,48 @@
+//! Error types shared across the crate.
+
+use thiserror::Error;
+
+/// The single error type returned by this crate's fallible operations.
+///
+/// Keeping one error enum (rather than `eprintln!` + `process::exit`) lets
+/// the parsing and networking functions be reused as a library and unit
+/// tested, with the CLI only responsible for formatting the error at the
+/// top level.
+#[derive(Debug, Error)]
+pub enum TorrentError {
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("invalid bencode: {0}")]
+    InvalidBencode(String),
+
+    #[error("invalid pieces length: not a multiple of 20 bytes")]
+    InvalidPiecesLength,
+
+    #[error("torrent file is missing an 'info' dictionary")]
+    MissingInfoDict,
+
+    #[error("failed to parse .torrent file: {0}")]
+    InvalidTorrentFile(String),
+
+    #[error("tracker request failed: {0}")]
+    Tracker(String),
+
+    #[error("tracker reported failure: {0}")]
+    TrackerFailure(String),
+
+    #[error("not a valid magnet link: {0}")]
+    InvalidMagnetLink(String),
+
+    #[error("unknown command: {0}")]
+    UnknownCommand(String),
+
+    #[error("usage: {0}")]
+    MissingArgument(String),
+
+    #[error("torrent has no announce URL to contact (trackerless/DHT-only torrent)")]
+    NoAnnounceUrl,
+
+    #[error("handshake failed: {0}")]
+    HandshakeFailed(String),
+}
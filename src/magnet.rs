@@ -0,0 +1,80 @@
+//! BEP 9 magnet link generation and parsing.
+
+use crate::torrent::{to_hex, Torrent};
+use crate::tracker::url_encode_bytes;
+
+/// A parsed magnet link.
+///
+/// Magnet links carry no `info` dict, so there's no piece data here - just
+/// enough to identify the torrent and reach trackers. Fetching the rest of
+/// the metainfo from peers is future work.
+#[derive(Debug, Clone)]
+pub struct MagnetLink {
+    pub info_hash_hex: String,
+    pub display_name: Option<String>,
+    pub trackers: Vec<String>,
+}
+
+/// Builds a `magnet:?xt=urn:btih:...` URI from a parsed `.torrent` and its
+/// info hash.
+pub fn build(torrent: &Torrent, info_hash: &[u8; 20]) -> String {
+    match &torrent.announce {
+        Some(announce) => format!(
+            "magnet:?xt=urn:btih:{}&dn={}&tr={}",
+            to_hex(info_hash),
+            url_encode_bytes(torrent.info.name.as_bytes()),
+            url_encode_bytes(announce.as_bytes()),
+        ),
+        None => format!(
+            "magnet:?xt=urn:btih:{}&dn={}",
+            to_hex(info_hash),
+            url_encode_bytes(torrent.info.name.as_bytes()),
+        ),
+    }
+}
+
+/// Parses a magnet URI into its `xt` (info hash), `dn`, and `tr` components.
+pub fn parse(uri: &str) -> Option<MagnetLink> {
+    let query = uri.strip_prefix("magnet:?")?;
+
+    let mut info_hash_hex = None;
+    let mut display_name = None;
+    let mut trackers = Vec::new();
+
+    for pair in query.split('&') {
+        let (key, value) = pair.split_once('=')?;
+        let value = url_decode(value);
+        match key {
+            "xt" => info_hash_hex = value.strip_prefix("urn:btih:").map(str::to_owned),
+            "dn" => display_name = Some(value),
+            "tr" => trackers.push(value),
+            _ => {}
+        }
+    }
+
+    Some(MagnetLink {
+        info_hash_hex: info_hash_hex?,
+        display_name,
+        trackers,
+    })
+}
+
+/// Decodes `%XX` percent-escapes in a query component.
+fn url_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len() {
+            let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).unwrap_or("");
+            if let Ok(byte) = u8::from_str_radix(hex, 16) {
+                out.push(byte);
+                i += 3;
+                continue;
+            }
+        }
+        out.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
@@ -0,0 +1,124 @@
+//! BitTorrent peer wire protocol: handshake and message framing.
+
+use crate::error::TorrentError;
+use std::io::{self, Read, Write};
+use std::net::TcpStream;
+
+const PROTOCOL: &[u8] = b"BitTorrent protocol";
+
+/// Performs the 68-byte BitTorrent handshake over an already-connected
+/// stream and returns the peer's 20-byte peer id.
+///
+/// Rejects a reply that doesn't echo back the same protocol header or info
+/// hash we sent - either means we're talking to the wrong peer entirely, or
+/// to one that doesn't have the torrent we asked about.
+pub fn handshake(
+    stream: &mut TcpStream,
+    info_hash: &[u8; 20],
+    peer_id: &[u8; 20],
+) -> Result<[u8; 20], TorrentError> {
+    let mut request = Vec::with_capacity(68);
+    request.push(19u8);
+    request.extend_from_slice(PROTOCOL);
+    request.extend_from_slice(&[0u8; 8]);
+    request.extend_from_slice(info_hash);
+    request.extend_from_slice(peer_id);
+
+    stream.write_all(&request)?;
+
+    let mut response = [0u8; 68];
+    stream.read_exact(&mut response)?;
+
+    if response[0] != 19 || &response[1..20] != PROTOCOL {
+        return Err(TorrentError::HandshakeFailed(
+            "peer did not reply with the BitTorrent protocol header".to_string(),
+        ));
+    }
+    if &response[28..48] != info_hash {
+        return Err(TorrentError::HandshakeFailed(
+            "peer echoed back a different info hash than we sent".to_string(),
+        ));
+    }
+
+    let mut their_peer_id = [0u8; 20];
+    their_peer_id.copy_from_slice(&response[48..68]);
+    Ok(their_peer_id)
+}
+
+/// Known peer wire message ids. `Unknown` preserves any id we don't model
+/// yet instead of failing to parse the message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MessageId {
+    Choke,
+    Unchoke,
+    Interested,
+    NotInterested,
+    Have,
+    Bitfield,
+    Request,
+    Piece,
+    Cancel,
+    Unknown(u8),
+}
+
+impl From<u8> for MessageId {
+    fn from(id: u8) -> Self {
+        match id {
+            0 => MessageId::Choke,
+            1 => MessageId::Unchoke,
+            2 => MessageId::Interested,
+            3 => MessageId::NotInterested,
+            4 => MessageId::Have,
+            5 => MessageId::Bitfield,
+            6 => MessageId::Request,
+            7 => MessageId::Piece,
+            8 => MessageId::Cancel,
+            other => MessageId::Unknown(other),
+        }
+    }
+}
+
+/// A length-prefixed peer wire message: a 4-byte big-endian length followed
+/// by a 1-byte message id and a payload.
+#[derive(Debug)]
+pub struct Message {
+    pub id: MessageId,
+    pub payload: Vec<u8>,
+}
+
+/// Largest message length we'll allocate a buffer for.
+///
+/// Real wire messages (even `bitfield`/`piece`) stay well under this; a
+/// length near this bound or above is a malicious or corrupt peer trying to
+/// force a huge allocation before we've validated anything about the
+/// message.
+const MAX_MESSAGE_LEN: usize = 256 * 1024;
+
+/// Reads a single message from the stream, or `None` if it was a
+/// zero-length keep-alive.
+pub fn read_message(stream: &mut TcpStream) -> io::Result<Option<Message>> {
+    let mut length_buf = [0u8; 4];
+    stream.read_exact(&mut length_buf)?;
+    let length = u32::from_be_bytes(length_buf) as usize;
+
+    if length == 0 {
+        return Ok(None);
+    }
+    if length > MAX_MESSAGE_LEN {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("peer message length {} exceeds the {} byte limit", length, MAX_MESSAGE_LEN),
+        ));
+    }
+
+    let mut id_buf = [0u8; 1];
+    stream.read_exact(&mut id_buf)?;
+
+    let mut payload = vec![0u8; length - 1];
+    stream.read_exact(&mut payload)?;
+
+    Ok(Some(Message {
+        id: MessageId::from(id_buf[0]),
+        payload,
+    }))
+}
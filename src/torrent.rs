@@ -0,0 +1,270 @@
+//! Torrent metainfo parsing.
+
+use crate::bencode::{self, Value};
+use crate::error::TorrentError;
+use serde::Deserialize;
+use sha1::{Digest, Sha1};
+
+/// Structure representing the Torrent metainfo file.
+#[derive(Debug, Deserialize)]
+pub struct Torrent {
+    pub announce: Option<String>,
+    #[serde(rename = "announce-list")]
+    pub announce_list: Option<Vec<Vec<String>>>,
+    pub info: Info,
+    #[serde(rename = "creation date")]
+    pub creation_date: Option<i64>,
+    #[serde(rename = "created by")]
+    pub created_by: Option<String>,
+    pub comment: Option<String>,
+}
+
+/// Structure for the "info" dictionary.
+///
+/// Models both single-file torrents (`length`) and multi-file torrents
+/// (`files`) - a v1 or hybrid torrent has exactly one of the two, while a
+/// v2-only torrent has neither (see [`Info::is_v1`]).
+#[derive(Debug, Deserialize)]
+pub struct Info {
+    pub name: String,
+    #[serde(rename = "piece length")]
+    pub piece_length: u64,
+    #[serde(with = "serde_bytes", default)]
+    pub pieces: Vec<u8>,
+    pub length: Option<u64>,
+    pub files: Option<Vec<FileEntry>>,
+}
+
+/// One entry of a multi-file torrent's `files` list.
+#[derive(Debug, Deserialize)]
+pub struct FileEntry {
+    pub length: u64,
+    pub path: Vec<String>,
+}
+
+impl Info {
+    /// Total content length, summed across files for multi-file torrents.
+    pub fn total_length(&self) -> u64 {
+        match &self.files {
+            Some(files) => files.iter().map(|f| f.length).sum(),
+            None => self.length.unwrap_or(0),
+        }
+    }
+
+    pub fn is_multi_file(&self) -> bool {
+        self.files.is_some()
+    }
+
+    /// Whether this `info` dict models a v1 layout (`length` or `files`).
+    pub fn is_v1(&self) -> bool {
+        self.length.is_some() || self.files.is_some()
+    }
+}
+
+/// Computes the info hash by SHA-1ing the exact bytes of the `info`
+/// dictionary as they appeared in the original file.
+///
+/// We deliberately don't re-bencode the parsed `Info` struct: `Info` only
+/// models a handful of keys, so any torrent carrying extra ones (`private`,
+/// `md5sum`, ...) would silently drop them and produce a different hash.
+/// Hashing the original slice is correct regardless of which keys the
+/// torrent includes or what order they're in.
+pub fn calculate_info_hash(info_bytes: &[u8]) -> [u8; 20] {
+    let mut hasher = Sha1::new();
+    hasher.update(info_bytes);
+    hasher.finalize().into()
+}
+
+/// Computes the BitTorrent v2 info hash: SHA-256 over the same `info`
+/// dictionary bytes used for the v1 SHA-1 hash.
+pub fn calculate_info_hash_v2(info_bytes: &[u8]) -> [u8; 32] {
+    use sha2::{Digest, Sha256};
+
+    let mut hasher = Sha256::new();
+    hasher.update(info_bytes);
+    hasher.finalize().into()
+}
+
+/// BitTorrent version detected for a torrent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Version {
+    V1,
+    V2,
+    Hybrid,
+}
+
+impl std::fmt::Display for Version {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let label = match self {
+            Version::V1 => "v1",
+            Version::V2 => "v2",
+            Version::Hybrid => "hybrid",
+        };
+        write!(f, "{}", label)
+    }
+}
+
+/// One leaf entry flattened out of a v2 `file tree` dictionary.
+#[derive(Debug, Clone)]
+pub struct FileTreeEntry {
+    pub path: Vec<String>,
+    pub length: u64,
+    pub pieces_root: Option<Vec<u8>>,
+}
+
+/// V2 metainfo extracted from the `info` dictionary, if present.
+#[derive(Debug, Clone, Default)]
+pub struct V2Info {
+    pub meta_version: Option<i64>,
+    pub files: Vec<FileTreeEntry>,
+}
+
+impl V2Info {
+    pub fn is_present(&self) -> bool {
+        self.meta_version == Some(2) && !self.files.is_empty()
+    }
+
+    pub fn total_length(&self) -> u64 {
+        self.files.iter().map(|f| f.length).sum()
+    }
+}
+
+/// Parses `meta version` and the flattened `file tree` leaves straight from
+/// the raw `info` dict bytes.
+///
+/// `file tree` nests path components to arbitrary depth with a leaf marked
+/// by an empty-string key, which doesn't map cleanly onto a fixed serde
+/// struct - so, like `nodes`, it's walked directly from the bencode `Value`.
+pub fn parse_v2_info(info_bytes: &[u8]) -> V2Info {
+    let Ok((info_value, _)) = bencode::decode(info_bytes) else {
+        return V2Info::default();
+    };
+
+    let meta_version = info_value.get("meta version").and_then(Value::as_int);
+
+    let mut files = Vec::new();
+    if let Some(tree) = info_value.get("file tree") {
+        walk_file_tree(tree, &mut Vec::new(), &mut files);
+    }
+
+    V2Info { meta_version, files }
+}
+
+fn walk_file_tree(value: &Value, path: &mut Vec<String>, out: &mut Vec<FileTreeEntry>) {
+    let Some(dict) = value.as_dict() else {
+        return;
+    };
+
+    for (key, child) in dict {
+        if key.is_empty() {
+            if let Some(length) = child.get("length").and_then(Value::as_int) {
+                let pieces_root = child
+                    .get("pieces root")
+                    .and_then(Value::as_bytes)
+                    .map(|b| b.to_vec());
+                out.push(FileTreeEntry {
+                    path: path.clone(),
+                    length: length as u64,
+                    pieces_root,
+                });
+            }
+            continue;
+        }
+
+        path.push(String::from_utf8_lossy(key).into_owned());
+        walk_file_tree(child, path, out);
+        path.pop();
+    }
+}
+
+/// Detects whether a torrent is v1-only, v2-only, or hybrid (both a v1
+/// `pieces`/`length` layout and a v2 `file tree`).
+pub fn detect_version(info: &Info, v2: &V2Info) -> Version {
+    match (info.is_v1(), v2.is_present()) {
+        (true, true) => Version::Hybrid,
+        (false, true) => Version::V2,
+        _ => Version::V1,
+    }
+}
+
+/// Function to extract and format SHA-1 piece hashes.
+pub fn extract_piece_hashes(pieces: &[u8]) -> Result<Vec<String>, TorrentError> {
+    const SHA1_HASH_SIZE: usize = 20;
+
+    if !pieces.len().is_multiple_of(SHA1_HASH_SIZE) {
+        return Err(TorrentError::InvalidPiecesLength);
+    }
+
+    Ok(pieces.chunks(SHA1_HASH_SIZE).map(to_hex).collect())
+}
+
+/// Formats bytes as a lowercase hex string.
+pub fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// A DHT bootstrap node from the `nodes` key: a `[host, port]` tuple.
+#[derive(Debug, Clone)]
+pub struct Node {
+    pub host: String,
+    pub port: u16,
+}
+
+/// Parses the top-level `nodes` key, if present, straight from the raw
+/// bencode `Value` rather than via serde.
+///
+/// In the wild these `[host, port]` tuples show up with the wrong arity or
+/// element types, so each entry is decoded best-effort and malformed ones
+/// are skipped instead of failing the whole torrent.
+pub fn parse_nodes(buffer: &[u8]) -> Vec<Node> {
+    let Ok(Some((start, end))) = bencode::find_top_level_value_span(buffer, "nodes") else {
+        return Vec::new();
+    };
+    let Ok((value, _)) = bencode::decode(&buffer[start..end]) else {
+        return Vec::new();
+    };
+    let Some(entries) = value.as_list() else {
+        return Vec::new();
+    };
+
+    entries.iter().filter_map(node_from_value).collect()
+}
+
+fn node_from_value(entry: &Value) -> Option<Node> {
+    let pair = entry.as_list()?;
+    let host = pair.first()?.as_bytes()?;
+    let host = String::from_utf8(host.to_vec()).ok()?;
+    let port = pair.get(1)?.as_int()?;
+    Some(Node {
+        host,
+        port: u16::try_from(port).ok()?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn calculate_info_hash_matches_known_vector() {
+        let info = b"d6:lengthi10e4:name4:test12:piece lengthi10e6:pieces20:aaaaaaaaaaaaaaaaaaaae";
+        let hash = calculate_info_hash(info);
+        assert_eq!(to_hex(&hash), "e1789c97788cfe02053169bbaca683a2bda85dcc");
+    }
+
+    #[test]
+    fn extract_piece_hashes_splits_into_20_byte_chunks() {
+        let pieces = [0u8; 40];
+        let hashes = extract_piece_hashes(&pieces).unwrap();
+        assert_eq!(hashes, vec!["0".repeat(40), "0".repeat(40)]);
+    }
+
+    #[test]
+    fn extract_piece_hashes_rejects_length_not_a_multiple_of_20() {
+        let pieces = [0u8; 25];
+        assert!(matches!(
+            extract_piece_hashes(&pieces),
+            Err(TorrentError::InvalidPiecesLength)
+        ));
+    }
+}
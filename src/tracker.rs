@@ -0,0 +1,116 @@
+//! HTTP tracker announce client.
+
+use crate::bencode::{self, Value};
+use crate::error::TorrentError;
+use rand::Rng;
+use std::net::Ipv4Addr;
+
+pub const PEER_ID_LEN: usize = 20;
+
+/// Generates a random 20-byte peer id for this client instance.
+pub fn generate_peer_id() -> [u8; PEER_ID_LEN] {
+    let mut id = [0u8; PEER_ID_LEN];
+    rand::thread_rng().fill(&mut id);
+    id
+}
+
+/// A peer address parsed out of a tracker's compact `peers` field.
+#[derive(Debug, Clone, Copy)]
+pub struct Peer {
+    pub ip: Ipv4Addr,
+    pub port: u16,
+}
+
+impl std::fmt::Display for Peer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.ip, self.port)
+    }
+}
+
+/// A decoded tracker announce response.
+#[derive(Debug)]
+pub struct AnnounceResponse {
+    pub interval: Option<i64>,
+    pub peers: Vec<Peer>,
+    pub failure_reason: Option<String>,
+}
+
+/// Performs a tracker GET announce and returns the parsed response.
+pub fn announce(
+    announce_url: &str,
+    info_hash: &[u8; 20],
+    peer_id: &[u8; PEER_ID_LEN],
+    port: u16,
+    left: u64,
+) -> Result<AnnounceResponse, TorrentError> {
+    // Private trackers often embed a query string in the announce URL itself
+    // (e.g. a passkey: `http://host/announce?passkey=SECRET`), so the
+    // separator for our own query params has to match whatever's already
+    // there instead of always being `?`.
+    let separator = if announce_url.contains('?') { '&' } else { '?' };
+    let url = format!(
+        "{}{}info_hash={}&peer_id={}&port={}&uploaded=0&downloaded=0&left={}&compact=1",
+        announce_url,
+        separator,
+        url_encode_bytes(info_hash),
+        url_encode_bytes(peer_id),
+        port,
+        left,
+    );
+
+    let response =
+        reqwest::blocking::get(&url).map_err(|e| TorrentError::Tracker(e.to_string()))?;
+    let body = response
+        .bytes()
+        .map_err(|e| TorrentError::Tracker(e.to_string()))?;
+    let (value, _) = bencode::decode(&body)?;
+
+    let failure_reason = value
+        .get("failure reason")
+        .and_then(Value::as_bytes)
+        .map(|b| String::from_utf8_lossy(b).into_owned());
+
+    let interval = value.get("interval").and_then(Value::as_int);
+
+    let peers = value
+        .get("peers")
+        .and_then(Value::as_bytes)
+        .map(parse_compact_peers)
+        .unwrap_or_default();
+
+    Ok(AnnounceResponse {
+        interval,
+        peers,
+        failure_reason,
+    })
+}
+
+/// Percent-encodes raw bytes for use in a URL query string.
+///
+/// Values like the info hash and peer id are raw 20-byte strings, not valid
+/// UTF-8, so this encodes byte-by-byte instead of going through a `&str`
+/// based percent-encoder.
+pub(crate) fn url_encode_bytes(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(bytes.len() * 3);
+    for &b in bytes {
+        match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                out.push(b as char);
+            }
+            _ => out.push_str(&format!("%{:02X}", b)),
+        }
+    }
+    out
+}
+
+/// Parses a tracker's compact `peers` field: every 6 bytes is a 4-byte
+/// big-endian IPv4 address followed by a 2-byte big-endian port.
+fn parse_compact_peers(bytes: &[u8]) -> Vec<Peer> {
+    bytes
+        .chunks_exact(6)
+        .map(|chunk| Peer {
+            ip: Ipv4Addr::new(chunk[0], chunk[1], chunk[2], chunk[3]),
+            port: u16::from_be_bytes([chunk[4], chunk[5]]),
+        })
+        .collect()
+}